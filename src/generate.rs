@@ -15,6 +15,7 @@ pub(crate) enum GetType {
     String_,
     Slice(syn::TypeSlice),
     Option_(Punctuated<GenericArgument, Comma>),
+    Smart(syn::Type),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -27,6 +28,43 @@ pub(crate) enum ClrMethod {
     None_,
 }
 
+/// Convert an UpperCamelCase identifier (e.g. a variant name) into `snake_case`.
+///
+/// Acronym runs are kept together (`HTTPError` -> `http_error`, not
+/// `h_t_t_p_error`): a word boundary is only inserted before an uppercase
+/// letter that follows a lowercase/digit, or before the last letter of an
+/// uppercase run when it's followed by a lowercase letter.
+pub(crate) fn to_snake_case(ident: &syn::Ident) -> String {
+    let chars: Vec<char> = ident.to_string().chars().collect();
+    let mut snake = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            let prev = if i > 0 { chars.get(i - 1) } else { None };
+            let next = chars.get(i + 1);
+            let boundary = match prev {
+                Some(prev) => {
+                    prev.is_lowercase()
+                        || prev.is_ascii_digit()
+                        || (prev.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+                }
+                None => false,
+            };
+            if boundary {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+/// The `snake_case` name of the `is_<variant>` predicate generated for a variant.
+pub(crate) fn variant_predicate_name(ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("is_{}", to_snake_case(ident)), ident.span())
+}
+
 pub(crate) enum FieldType {
     Number,
     Boolean,
@@ -35,6 +73,9 @@ pub(crate) enum FieldType {
     Array(syn::TypeArray),
     Vector(syn::Type),
     Option_(Punctuated<GenericArgument, Comma>),
+    /// `Box<T>`, `Rc<T>`, `Arc<T>`, or `Cow<'_, T>`: all four `Deref` to `T`, so a
+    /// getter can hand out `&T` directly instead of leaking the wrapper.
+    Smart(syn::Type),
     Unhandled(Option<String>),
 }
 
@@ -70,6 +111,7 @@ impl GetType {
                 }
                 GetType::Option_(inner_type.clone())
             }
+            FieldType::Smart(inner_type) => GetType::Smart(inner_type.clone()),
             FieldType::Unhandled(_) => GetType::Ref,
         }
     }
@@ -83,6 +125,10 @@ impl ClrMethod {
             FieldType::Boolean | FieldType::Character => ClrMethod::SetDefault,
             FieldType::String_ | FieldType::Vector(_) => ClrMethod::CallClear,
             FieldType::Array(_) => ClrMethod::FillWithDefault,
+            // The generated clr statement still operates on `self.#field_name`, i.e. the
+            // wrapper itself, not the value it points to: `Rc`/`Arc` don't even implement
+            // `DerefMut`, so there's no mutating strategy that could work here in general.
+            FieldType::Smart(_) => ClrMethod::None_,
             FieldType::Unhandled(Some(ref type_name)) => match type_name.as_str() {
                 "String" | "PathBuf" | "Vec" | "VecDeque" | "LinkedList" | "HashMap"
                 | "BTreeMap" | "HashSet" | "BTreeSet" | "BinaryHeap" => ClrMethod::CallClear,
@@ -128,6 +174,27 @@ impl FieldType {
                                 unreachable!()
                             }
                         }
+                        "Box" | "Rc" | "Arc" | "Cow" => {
+                            let inner_type = if let syn::PathArguments::AngleBracketed(inner) =
+                                &type_path.path.segments[0].arguments
+                            {
+                                inner.args.iter().find_map(|arg| match arg {
+                                    syn::GenericArgument::Type(inner_type) => {
+                                        Some(inner_type.clone())
+                                    }
+                                    _ => None,
+                                })
+                            } else {
+                                None
+                            };
+                            match inner_type {
+                                Some(inner_type) => FieldType::Smart(inner_type),
+                                None => {
+                                    let type_name = segs.last().cloned().unwrap().ident.to_string();
+                                    FieldType::Unhandled(Some(type_name))
+                                }
+                            }
+                        }
                         _ => {
                             let type_name = segs.last().cloned().unwrap().ident.to_string();
                             FieldType::Unhandled(Some(type_name))
@@ -8,15 +8,20 @@
 
 extern crate proc_macro;
 
-use quote::quote;
+use std::collections::HashMap;
+
+use quote::{format_ident, quote};
 use syn::{spanned::Spanned, Error as SynError};
 
 mod generate;
 mod parse;
 
 use crate::{
-    generate::{ClrMethod, FieldType, GetType},
-    parse::{ClrScopeConf, ContainerDef, CrateConfDef, FieldDef, GetTypeConf, SetTypeConf},
+    generate::{to_snake_case, variant_predicate_name, ClrMethod, FieldType, GetType},
+    parse::{
+        ClrScopeConf, ContainerData, ContainerDef, CrateConfDef, FieldDef, GetTypeConf, NameConf,
+        NewConf, SetTypeConf, VariantDef, VariantStyle,
+    },
 };
 
 /// Set a global default setting for all `#[derive(Property)]` in the same crate.
@@ -71,19 +76,42 @@ pub fn property_default(
     expanded.into()
 }
 
-/// Generate several common methods for structs automatically.
+/// Generate several common methods for structs, and variant predicates and field
+/// getters for enums, automatically.
 #[proc_macro_derive(Property, attributes(property))]
 pub fn derive_property(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let property = syn::parse_macro_input!(input as ContainerDef);
     let expanded = {
         let name = &property.name;
         let (impl_generics, type_generics, where_clause_opt) = property.generics.split_for_impl();
-        let methods = property.fields.iter().fold(Vec::new(), |mut r, f| {
-            if !f.conf.skip {
-                r.append(&mut derive_property_for_field(f));
+        let methods = match &property.data {
+            ContainerData::Struct(fields) => {
+                let mut methods = fields.iter().fold(Vec::new(), |mut r, f| {
+                    if !f.conf.skip {
+                        r.append(&mut derive_property_for_field(f));
+                    }
+                    r
+                });
+                methods.append(&mut derive_new_methods(fields, &property.new));
+                methods
             }
-            r
-        });
+            ContainerData::Enum(variants) => {
+                let field_name_counts =
+                    variants.iter().flat_map(|v| &v.fields).fold(
+                        HashMap::<String, usize>::new(),
+                        |mut counts, f| {
+                            if !f.conf.skip {
+                                *counts.entry(f.ident.to_string()).or_insert(0) += 1;
+                            }
+                            counts
+                        },
+                    );
+                variants.iter().fold(Vec::new(), |mut r, v| {
+                    r.append(&mut derive_property_for_variant(name, v, &field_name_counts));
+                    r
+                })
+            }
+        };
         let impl_methods = quote!(
             impl #impl_generics #name #type_generics #where_clause_opt {
                 #(#[inline] #methods)*
@@ -100,8 +128,11 @@ pub fn derive_property(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
 
 fn implement_traits(property: &ContainerDef) -> Option<proc_macro2::TokenStream> {
     let name = &property.name;
-    let mut ordered: Vec<_> = property
-        .fields
+    let fields = match &property.data {
+        ContainerData::Struct(fields) => fields,
+        ContainerData::Enum(_) => return None,
+    };
+    let mut ordered: Vec<_> = fields
         .iter()
         .filter(|f| f.conf.ord.number.is_some())
         .collect();
@@ -141,6 +172,23 @@ fn implement_traits(property: &ContainerDef) -> Option<proc_macro2::TokenStream>
             }));
             r
         });
+        let ord_stmt = ordered.iter().fold(Vec::new(), |mut r, f| {
+            let field_name = &f.ident;
+            r.push(if f.conf.ord.sort_type.is_ascending() {
+                quote!(let result = ::core::cmp::Ord::cmp(&self.#field_name, &other.#field_name);)
+            } else {
+                quote!(let result = ::core::cmp::Ord::cmp(&other.#field_name, &self.#field_name);)
+            });
+            r.push(quote!(if result != ::core::cmp::Ordering::Equal {
+                return result;
+            }));
+            r
+        });
+        let hash_stmt = ordered.iter().fold(Vec::new(), |mut r, f| {
+            let field_name = &f.ident;
+            r.push(quote!(::core::hash::Hash::hash(&self.#field_name, state);));
+            r
+        });
         let stmts = quote!(
             impl PartialEq for #name {
                 fn eq(&self, other: &Self) -> bool {
@@ -148,17 +196,165 @@ fn implement_traits(property: &ContainerDef) -> Option<proc_macro2::TokenStream>
                 }
             }
 
+            impl Eq for #name {}
+
             impl PartialOrd for #name {
                 fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
                     #(#partial_ord_stmt)*
                     Some(::core::cmp::Ordering::Equal)
                 }
             }
+
+            impl Ord for #name {
+                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                    #(#ord_stmt)*
+                    ::core::cmp::Ordering::Equal
+                }
+            }
+
+            impl ::core::hash::Hash for #name {
+                fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                    #(#hash_stmt)*
+                }
+            }
         );
         Some(stmts)
     }
 }
 
+fn derive_property_for_variant(
+    container_name: &syn::Ident,
+    variant: &VariantDef,
+    field_name_counts: &HashMap<String, usize>,
+) -> Vec<proc_macro2::TokenStream> {
+    let mut property = Vec::new();
+    let variant_name = &variant.ident;
+    let predicate_name = variant_predicate_name(variant_name);
+    let matches_pattern = match variant.style {
+        VariantStyle::Unit => quote!(#container_name::#variant_name),
+        VariantStyle::Unnamed => quote!(#container_name::#variant_name(..)),
+        VariantStyle::Named => quote!(#container_name::#variant_name { .. }),
+    };
+    property.push(quote!(
+        fn #predicate_name(&self) -> bool {
+            matches!(self, #matches_pattern)
+        }
+    ));
+    for field in &variant.fields {
+        if !field.conf.skip {
+            if let Some(ts) = derive_property_for_variant_field(
+                container_name,
+                variant_name,
+                field,
+                field_name_counts,
+            ) {
+                property.push(ts);
+            }
+        }
+    }
+    property
+}
+
+fn derive_property_for_variant_field(
+    container_name: &syn::Ident,
+    variant_name: &syn::Ident,
+    field: &FieldDef,
+    field_name_counts: &HashMap<String, usize>,
+) -> Option<proc_macro2::TokenStream> {
+    let field_type = &field.ty;
+    let field_name = &field.ident;
+    let get_conf = &field.conf.get;
+    let visibility = get_conf.vis.to_ts()?;
+    // Two variants can declare a field with the same name (e.g. `Created { id }` /
+    // `Updated { id }`); emitting both as bare `fn id(&self)` in the same impl block
+    // is a duplicate-definition error, so namespace the getter by variant whenever the
+    // field name isn't unique across the enum and the user hasn't picked a name of
+    // their own via `#[property(get(name = ".."))]`.
+    let method_name = match &get_conf.name {
+        name @ NameConf::Custom(_) => name.complete(field_name),
+        name @ NameConf::Default_(_) => {
+            if field_name_counts.get(&field_name.to_string()).copied().unwrap_or(0) > 1 {
+                format_ident!("{}_{}", to_snake_case(variant_name), field_name)
+            } else {
+                name.complete(field_name)
+            }
+        }
+    };
+    let method_name = &method_name;
+    let prop_field_type = FieldType::from_type(field_type);
+    let get_type = match get_conf.typ {
+        GetTypeConf::Auto => GetType::from_field_type(&prop_field_type),
+        GetTypeConf::Ref => GetType::Ref,
+        GetTypeConf::Copy_ => GetType::Copy_,
+        GetTypeConf::Clone_ => GetType::Clone_,
+    };
+    Some(match get_type {
+        GetType::Ref => quote!(
+            #visibility fn #method_name(&self) -> Option<&#field_type> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    Some(#field_name)
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::Copy_ => quote!(
+            #visibility fn #method_name(&self) -> Option<#field_type> {
+                if let #container_name::#variant_name { #field_name, .. } = self {
+                    Some(*#field_name)
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::Clone_ => quote!(
+            #visibility fn #method_name(&self) -> Option<#field_type> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    Some(#field_name.clone())
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::String_ => quote!(
+            #visibility fn #method_name(&self) -> Option<&str> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    Some(&#field_name[..])
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::Slice(field_type) => quote!(
+            #visibility fn #method_name(&self) -> Option<&#field_type> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    Some(&#field_name[..])
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::Option_(field_type) => quote!(
+            #visibility fn #method_name(&self) -> Option<&#field_type> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    #field_name.as_ref()
+                } else {
+                    None
+                }
+            }
+        ),
+        GetType::Smart(field_type) => quote!(
+            #visibility fn #method_name(&self) -> Option<&#field_type> {
+                if let #container_name::#variant_name { ref #field_name, .. } = self {
+                    Some(&**#field_name)
+                } else {
+                    None
+                }
+            }
+        ),
+    })
+}
+
 fn derive_property_for_field(field: &FieldDef) -> Vec<proc_macro2::TokenStream> {
     let mut property = Vec::new();
     let field_type = &field.ty;
@@ -204,6 +400,11 @@ fn derive_property_for_field(field: &FieldDef) -> Vec<proc_macro2::TokenStream>
                     self.#field_name.as_ref()
                 }
             ),
+            GetType::Smart(field_type) => quote!(
+                #visibility fn #method_name(&self) -> &#field_type {
+                    &*self.#field_name
+                }
+            ),
         }
     }) {
         property.push(ts);
@@ -325,6 +526,26 @@ fn derive_property_for_field(field: &FieldDef) -> Vec<proc_macro2::TokenStream>
     }) {
         property.push(ts);
     }
+    if let Some(visibility) = field_conf.with.vis.to_ts() {
+        let method_name = field_conf.with.name.complete(field_name);
+        let ref_method_name = format_ident!("{}_ref", method_name);
+        property.push(quote!(
+            #visibility fn #method_name<F, R>(&mut self, f: F) -> R
+            where
+                F: FnOnce(&mut #field_type) -> R,
+            {
+                f(&mut self.#field_name)
+            }
+        ));
+        property.push(quote!(
+            #visibility fn #ref_method_name<F, R>(&self, f: F) -> R
+            where
+                F: FnOnce(&#field_type) -> R,
+            {
+                f(&self.#field_name)
+            }
+        ));
+    }
     if let Some(ts) = field_conf.clr.vis.to_ts().and_then(|visibility| {
         let method_name = field_conf.clr.name.complete(field_name);
         let auto_clr_method = ClrMethod::from_field_type(&prop_field_type);
@@ -378,3 +599,141 @@ fn derive_property_for_field(field: &FieldDef) -> Vec<proc_macro2::TokenStream>
     }
     property
 }
+
+/// Generate the `new`/`try_new` constructors requested via `#[property(new(..))]`.
+///
+/// Every non-skipped field becomes a parameter using the same `Into`/`IntoIterator`
+/// shapes that setters already use; skipped fields are produced via
+/// `Default::default()`. Fields marked `#[property(fallible)]` additionally go
+/// through `TryInto` in `try_new`, short-circuiting on the first conversion error.
+fn derive_new_methods(fields: &[FieldDef], new_conf: &NewConf) -> Vec<proc_macro2::TokenStream> {
+    if !new_conf.enabled {
+        return Vec::new();
+    }
+    let new_name = &new_conf.name;
+    let try_new_name = format_ident!("try_{}", new_name);
+
+    let mut new_params = Vec::new();
+    let mut new_inits = Vec::new();
+    let mut try_new_generics = Vec::new();
+    let mut try_new_bounds = Vec::new();
+    let mut try_new_params = Vec::new();
+    let mut try_new_inits = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let field_name = &field.ident;
+        let field_type = &field.ty;
+        if field.conf.skip {
+            let init = quote!(#field_name: ::core::default::Default::default());
+            new_inits.push(init.clone());
+            try_new_inits.push(init);
+            continue;
+        }
+        let prop_field_type = FieldType::from_type(field_type);
+        // Mirror the setter (see `FieldType::Option_(..) if !field_conf.set.full_option` above):
+        // a field marked `set(full_option)` wants the raw `Option<T>` threaded through as-is,
+        // so `None` stays constructible; don't flatten it into `impl Into<T>` + `Some(..)`.
+        let flat_option_inner = match &prop_field_type {
+            FieldType::Option_(inner) if inner.len() == 1 && !field.conf.set.full_option => {
+                match inner.first() {
+                    Some(syn::GenericArgument::Type(inner_type)) => Some(inner_type.clone()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        if field.conf.fallible {
+            let generic = format_ident!("__T{}", index);
+            try_new_generics.push(quote!(#generic));
+            match (&prop_field_type, &flat_option_inner) {
+                (FieldType::Vector(inner), _) => {
+                    try_new_bounds.push(
+                        quote!(#generic: ::core::convert::TryInto<#inner>, E: ::core::convert::From<<#generic as ::core::convert::TryInto<#inner>>::Error>),
+                    );
+                    try_new_params.push(quote!(#field_name: impl IntoIterator<Item = #generic>));
+                    try_new_inits.push(quote!(
+                        #field_name: #field_name
+                            .into_iter()
+                            .map(|value| ::core::convert::TryInto::try_into(value).map_err(::core::convert::Into::into))
+                            .collect::<::core::result::Result<_, E>>()?
+                    ));
+                }
+                (FieldType::Option_(_), Some(inner)) => {
+                    try_new_bounds.push(
+                        quote!(#generic: ::core::convert::TryInto<#inner>, E: ::core::convert::From<<#generic as ::core::convert::TryInto<#inner>>::Error>),
+                    );
+                    try_new_params.push(quote!(#field_name: #generic));
+                    try_new_inits.push(quote!(
+                        #field_name: ::core::option::Option::Some(
+                            ::core::convert::TryInto::try_into(#field_name).map_err(::core::convert::Into::into)?
+                        )
+                    ));
+                }
+                _ => {
+                    try_new_bounds.push(
+                        quote!(#generic: ::core::convert::TryInto<#field_type>, E: ::core::convert::From<<#generic as ::core::convert::TryInto<#field_type>>::Error>),
+                    );
+                    try_new_params.push(quote!(#field_name: #generic));
+                    try_new_inits.push(quote!(
+                        #field_name: ::core::convert::TryInto::try_into(#field_name).map_err(::core::convert::Into::into)?
+                    ));
+                }
+            }
+        } else {
+            match (&prop_field_type, &flat_option_inner) {
+                (FieldType::Vector(inner), _) => {
+                    try_new_params.push(quote!(#field_name: impl IntoIterator<Item = impl Into<#inner>>));
+                    try_new_inits.push(quote!(
+                        #field_name: #field_name.into_iter().map(::core::convert::Into::into).collect()
+                    ));
+                }
+                (FieldType::Option_(_), Some(inner)) => {
+                    try_new_params.push(quote!(#field_name: impl Into<#inner>));
+                    try_new_inits.push(quote!(#field_name: ::core::option::Option::Some(#field_name.into())));
+                }
+                _ => {
+                    try_new_params.push(quote!(#field_name: impl Into<#field_type>));
+                    try_new_inits.push(quote!(#field_name: #field_name.into()));
+                }
+            }
+        }
+
+        match (&prop_field_type, &flat_option_inner) {
+            (FieldType::Vector(inner), _) => {
+                new_params.push(quote!(#field_name: impl IntoIterator<Item = impl Into<#inner>>));
+                new_inits.push(quote!(
+                    #field_name: #field_name.into_iter().map(::core::convert::Into::into).collect()
+                ));
+            }
+            (FieldType::Option_(_), Some(inner)) => {
+                new_params.push(quote!(#field_name: impl Into<#inner>));
+                new_inits.push(quote!(#field_name: ::core::option::Option::Some(#field_name.into())));
+            }
+            _ => {
+                new_params.push(quote!(#field_name: impl Into<#field_type>));
+                new_inits.push(quote!(#field_name: #field_name.into()));
+            }
+        }
+    }
+
+    let new_method = quote!(
+        fn #new_name(#(#new_params),*) -> Self {
+            Self {
+                #(#new_inits),*
+            }
+        }
+    );
+    let try_new_method = quote!(
+        fn #try_new_name<E #(, #try_new_generics)*>(#(#try_new_params),*) -> ::core::result::Result<Self, E>
+        where
+            #(#try_new_bounds,)*
+        {
+            ::core::result::Result::Ok(Self {
+                #(#try_new_inits),*
+            })
+        }
+    );
+
+    vec![new_method, try_new_method]
+}
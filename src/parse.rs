@@ -0,0 +1,640 @@
+// Copyright (C) 2019-2021 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::sync::{Mutex, OnceLock};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token::Comma,
+    Data, DeriveInput, Expr, Fields, Ident, Lit, Meta, Result as SynResult, Type,
+};
+
+fn default_conf() -> &'static Mutex<CrateConfDef> {
+    static DEFAULT_CONF: OnceLock<Mutex<CrateConfDef>> = OnceLock::new();
+    DEFAULT_CONF.get_or_init(|| Mutex::new(CrateConfDef::default()))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum VisConf {
+    #[default]
+    Inherit,
+    Disable,
+    Enable,
+    Public,
+    Crate_,
+    Private,
+}
+
+impl VisConf {
+    fn merge(self, fallback: Self) -> Self {
+        match self {
+            VisConf::Inherit => fallback,
+            _ => self,
+        }
+    }
+
+    /// Returns `None` if the method should not be generated at all, otherwise the
+    /// token stream to put before `fn`.
+    pub(crate) fn to_ts(self) -> Option<TokenStream> {
+        match self {
+            VisConf::Disable => None,
+            VisConf::Inherit | VisConf::Enable | VisConf::Private => Some(quote!()),
+            VisConf::Public => Some(quote!(pub)),
+            VisConf::Crate_ => Some(quote!(pub(crate))),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum NameConf {
+    Default_(&'static str),
+    Custom(Ident),
+}
+
+impl NameConf {
+    pub(crate) fn complete(&self, field_name: &Ident) -> Ident {
+        match self {
+            NameConf::Custom(name) => name.clone(),
+            NameConf::Default_(prefix) => {
+                if prefix.is_empty() {
+                    field_name.clone()
+                } else {
+                    Ident::new(&format!("{}{}", prefix, field_name), field_name.span())
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GetTypeConf {
+    Auto,
+    Ref,
+    Copy_,
+    Clone_,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SetTypeConf {
+    Ref,
+    Own,
+    None_,
+    Replace,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClrScopeConf {
+    Auto,
+    Option_,
+    All,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum SortTypeConf {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortTypeConf {
+    pub(crate) fn is_ascending(self) -> bool {
+        matches!(self, SortTypeConf::Ascending)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct GetConf {
+    pub(crate) vis: VisConf,
+    pub(crate) name: NameConf,
+    pub(crate) typ: GetTypeConf,
+}
+
+impl Default for GetConf {
+    fn default() -> Self {
+        Self {
+            vis: VisConf::Inherit,
+            name: NameConf::Default_(""),
+            typ: GetTypeConf::Auto,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SetConf {
+    pub(crate) vis: VisConf,
+    pub(crate) name: NameConf,
+    pub(crate) typ: SetTypeConf,
+    pub(crate) full_option: bool,
+}
+
+impl Default for SetConf {
+    fn default() -> Self {
+        Self {
+            vis: VisConf::Inherit,
+            name: NameConf::Default_("set_"),
+            typ: SetTypeConf::Ref,
+            full_option: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct MutConf {
+    pub(crate) vis: VisConf,
+    pub(crate) name: NameConf,
+}
+
+impl Default for MutConf {
+    fn default() -> Self {
+        Self {
+            vis: VisConf::Inherit,
+            name: NameConf::Default_("mut_"),
+        }
+    }
+}
+
+/// Config for the `with_<field>`/`with_<field>_ref` closure accessors generated by
+/// `#[property(with(..))]`.
+#[derive(Clone)]
+pub(crate) struct WithConf {
+    pub(crate) vis: VisConf,
+    pub(crate) name: NameConf,
+}
+
+impl Default for WithConf {
+    fn default() -> Self {
+        Self {
+            // Unlike get/set/mut_/clr, `with` is a new field mode that only activates
+            // via an explicit `#[property(with)]`/`#[property(with(..))]` attribute, or
+            // crate-wide via `#[property_default(with(enable))]`.
+            vis: VisConf::Disable,
+            name: NameConf::Default_("with_"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ClrConf {
+    pub(crate) vis: VisConf,
+    pub(crate) name: NameConf,
+    pub(crate) scope: ClrScopeConf,
+}
+
+impl Default for ClrConf {
+    fn default() -> Self {
+        Self {
+            vis: VisConf::Inherit,
+            name: NameConf::Default_("clear_"),
+            scope: ClrScopeConf::Auto,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct OrdConf {
+    pub(crate) number: Option<u32>,
+    pub(crate) sort_type: SortTypeConf,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct FieldConf {
+    pub(crate) skip: bool,
+    pub(crate) fallible: bool,
+    pub(crate) get: GetConf,
+    pub(crate) set: SetConf,
+    pub(crate) mut_: MutConf,
+    pub(crate) with: WithConf,
+    pub(crate) clr: ClrConf,
+    pub(crate) ord: OrdConf,
+}
+
+/// Container-level config for the `new`/`try_new` constructors emitted by
+/// `#[property(new(..))]`.
+#[derive(Clone)]
+pub(crate) struct NewConf {
+    pub(crate) enabled: bool,
+    pub(crate) name: Ident,
+}
+
+impl Default for NewConf {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: Ident::new("new", proc_macro2::Span::call_site()),
+        }
+    }
+}
+
+fn apply_new_meta(meta: &Meta, conf: &mut NewConf) -> SynResult<()> {
+    conf.enabled = true;
+    if let Meta::List(list) = meta {
+        let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+        for m in inner {
+            if let Meta::NameValue(nv) = &m {
+                if let Some(ident) = nv.path.get_ident() {
+                    if ident == "name" {
+                        if let Some(s) = lit_str_from_expr(&nv.value) {
+                            conf.name = Ident::new(&s, ident.span());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse the container-level `#[property(new(..))]` attribute, if any.
+fn parse_new_conf(attrs: &[syn::Attribute]) -> SynResult<NewConf> {
+    let mut conf = NewConf::default();
+    for attr in attrs {
+        if attr.path().is_ident("property") {
+            let metas = attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+            for meta in &metas {
+                if meta.path().is_ident("new") {
+                    apply_new_meta(meta, &mut conf)?;
+                }
+            }
+        }
+    }
+    Ok(conf)
+}
+
+/// The default configuration for the whole crate, set via `#[property_default(..)]`.
+///
+/// Only visibility can be overridden at the crate level: each scope is either left
+/// alone, or forced `disable`/`enable`, and that choice is used as the fallback
+/// whenever a container or a field does not say otherwise.
+#[derive(Clone, Default)]
+pub(crate) struct CrateConfDef {
+    get: VisConf,
+    set: VisConf,
+    mut_: VisConf,
+    with: VisConf,
+    clr: VisConf,
+}
+
+impl CrateConfDef {
+    pub(crate) fn set_default_conf(&self) {
+        let mut conf = default_conf().lock().unwrap();
+        *conf = self.clone();
+    }
+
+    fn apply_to(&self, conf: &mut FieldConf) {
+        conf.get.vis = conf.get.vis.merge(self.get);
+        conf.set.vis = conf.set.vis.merge(self.set);
+        conf.mut_.vis = conf.mut_.vis.merge(self.mut_);
+        // `with` starts out `Disable` rather than `Inherit`, so the crate-level choice
+        // takes precedence over that default instead of falling back to it.
+        conf.with.vis = self.with.merge(conf.with.vis);
+        conf.clr.vis = conf.clr.vis.merge(self.clr);
+    }
+}
+
+impl Parse for CrateConfDef {
+    fn parse(input: ParseStream) -> SynResult<Self> {
+        let mut conf = CrateConfDef::default();
+        let metas = Punctuated::<Meta, Comma>::parse_terminated(input)?;
+        for meta in metas {
+            apply_scope_meta(&meta, |scope, vis| match scope {
+                "get" => conf.get = vis,
+                "set" => conf.set = vis,
+                "mut_" => conf.mut_ = vis,
+                "with" => conf.with = vis,
+                "clr" => conf.clr = vis,
+                _ => {}
+            })?;
+        }
+        Ok(conf)
+    }
+}
+
+fn apply_scope_meta(
+    meta: &Meta,
+    mut setter: impl FnMut(&str, VisConf),
+) -> SynResult<()> {
+    if let Meta::List(list) = meta {
+        let scope = list.path.get_ident().map(|i| i.to_string());
+        let scope = match scope {
+            Some(s) => s,
+            None => return Ok(()),
+        };
+        let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+        for inner_meta in inner {
+            if inner_meta.path().is_ident("disable") {
+                setter(&scope, VisConf::Disable);
+            } else if inner_meta.path().is_ident("enable") {
+                setter(&scope, VisConf::Enable);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lit_str_from_expr(expr: &Expr) -> Option<String> {
+    if let Expr::Lit(expr_lit) = expr {
+        if let Lit::Str(ref s) = expr_lit.lit {
+            return Some(s.value());
+        }
+    }
+    None
+}
+
+/// Merge one `#[property(..)]` attribute's worth of settings into `conf`.
+fn apply_field_meta(meta: &Meta, conf: &mut FieldConf) -> SynResult<()> {
+    match meta {
+        Meta::Path(path) => {
+            if path.is_ident("skip") {
+                conf.skip = true;
+            } else if path.is_ident("fallible") {
+                conf.fallible = true;
+            } else if path.is_ident("with") {
+                conf.with.vis = VisConf::Enable;
+            }
+        }
+        Meta::List(list) => {
+            let Some(ident) = list.path.get_ident() else {
+                return Ok(());
+            };
+            match ident.to_string().as_str() {
+                "new" => {}
+                "get" => {
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_get_meta(&m, &mut conf.get)?;
+                    }
+                }
+                "set" => {
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_set_meta(&m, &mut conf.set)?;
+                    }
+                }
+                "mut_" => {
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_name_vis_meta(&m, &mut conf.mut_.vis, &mut conf.mut_.name)?;
+                    }
+                }
+                "with" => {
+                    if conf.with.vis == VisConf::Disable {
+                        conf.with.vis = VisConf::Enable;
+                    }
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_name_vis_meta(&m, &mut conf.with.vis, &mut conf.with.name)?;
+                    }
+                }
+                "clr" => {
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_clr_meta(&m, &mut conf.clr)?;
+                    }
+                }
+                "ord" => {
+                    let inner = list.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+                    for m in inner {
+                        apply_ord_meta(&m, &mut conf.ord)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Meta::NameValue(_) => {}
+    }
+    Ok(())
+}
+
+fn apply_name_vis_meta(meta: &Meta, vis: &mut VisConf, name: &mut NameConf) -> SynResult<()> {
+    match meta {
+        Meta::Path(path) => {
+            if path.is_ident("disable") {
+                *vis = VisConf::Disable;
+            } else if path.is_ident("enable") {
+                *vis = VisConf::Enable;
+            } else if path.is_ident("public") {
+                *vis = VisConf::Public;
+            } else if path.is_ident("private") {
+                *vis = VisConf::Private;
+            } else if path.is_ident("crate_") {
+                *vis = VisConf::Crate_;
+            }
+        }
+        Meta::NameValue(nv) => {
+            if let Some(ident) = nv.path.get_ident() {
+                if ident == "name" {
+                    if let Some(s) = lit_str_from_expr(&nv.value) {
+                        *name = NameConf::Custom(Ident::new(&s, ident.span()));
+                    }
+                }
+            }
+        }
+        Meta::List(_) => {}
+    }
+    Ok(())
+}
+
+fn apply_get_meta(meta: &Meta, conf: &mut GetConf) -> SynResult<()> {
+    match meta {
+        Meta::List(list) if list.path.is_ident("type") => {
+            let typ: Ident = list.parse_args()?;
+            conf.typ = match typ.to_string().as_str() {
+                "ref" => GetTypeConf::Ref,
+                "copy" => GetTypeConf::Copy_,
+                "clone" => GetTypeConf::Clone_,
+                _ => GetTypeConf::Auto,
+            };
+            Ok(())
+        }
+        other => apply_name_vis_meta(other, &mut conf.vis, &mut conf.name),
+    }
+}
+
+fn apply_set_meta(meta: &Meta, conf: &mut SetConf) -> SynResult<()> {
+    match meta {
+        Meta::List(list) if list.path.is_ident("type") => {
+            let typ: Ident = list.parse_args()?;
+            conf.typ = match typ.to_string().as_str() {
+                "own" => SetTypeConf::Own,
+                "none" => SetTypeConf::None_,
+                "replace" => SetTypeConf::Replace,
+                _ => SetTypeConf::Ref,
+            };
+            Ok(())
+        }
+        Meta::Path(path) if path.is_ident("full_option") => {
+            conf.full_option = true;
+            Ok(())
+        }
+        other => apply_name_vis_meta(other, &mut conf.vis, &mut conf.name),
+    }
+}
+
+fn apply_clr_meta(meta: &Meta, conf: &mut ClrConf) -> SynResult<()> {
+    match meta {
+        Meta::List(list) if list.path.is_ident("scope") => {
+            let scope: Ident = list.parse_args()?;
+            conf.scope = match scope.to_string().as_str() {
+                "option" => ClrScopeConf::Option_,
+                "all" => ClrScopeConf::All,
+                _ => ClrScopeConf::Auto,
+            };
+            Ok(())
+        }
+        other => apply_name_vis_meta(other, &mut conf.vis, &mut conf.name),
+    }
+}
+
+fn apply_ord_meta(meta: &Meta, conf: &mut OrdConf) -> SynResult<()> {
+    match meta {
+        Meta::Path(path) => {
+            if path.is_ident("asc") {
+                conf.sort_type = SortTypeConf::Ascending;
+            } else if path.is_ident("desc") {
+                conf.sort_type = SortTypeConf::Descending;
+            }
+        }
+        Meta::NameValue(nv) if nv.path.is_ident("number") => {
+            if let Expr::Lit(expr_lit) = &nv.value {
+                if let Lit::Int(ref n) = expr_lit.lit {
+                    conf.number = Some(n.base10_parse()?);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse every `#[property(..)]` attribute attached to an item (a container or a
+/// field) into a single, merged [`FieldConf`], starting from `base`.
+fn parse_property_attrs(attrs: &[syn::Attribute], base: FieldConf) -> SynResult<FieldConf> {
+    let mut conf = base;
+    for attr in attrs {
+        if attr.path().is_ident("property") {
+            let metas = attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)?;
+            for meta in &metas {
+                apply_field_meta(meta, &mut conf)?;
+            }
+        }
+    }
+    Ok(conf)
+}
+
+pub(crate) struct FieldDef {
+    pub(crate) ident: Ident,
+    pub(crate) ty: Type,
+    pub(crate) conf: FieldConf,
+}
+
+fn parse_named_fields(
+    fields: syn::FieldsNamed,
+    container_conf: &FieldConf,
+) -> SynResult<Vec<FieldDef>> {
+    fields
+        .named
+        .into_iter()
+        .map(|field| {
+            let ident = field.ident.clone().expect("named field has an ident");
+            let conf = parse_property_attrs(&field.attrs, container_conf.clone())?;
+            Ok(FieldDef {
+                ident,
+                ty: field.ty,
+                conf,
+            })
+        })
+        .collect()
+}
+
+/// How a `#[derive(Property)]` enum variant is shaped; only [`VariantStyle::Named`]
+/// carries fields that can have getters generated for them.
+pub(crate) enum VariantStyle {
+    Unit,
+    Unnamed,
+    Named,
+}
+
+pub(crate) struct VariantDef {
+    pub(crate) ident: Ident,
+    pub(crate) style: VariantStyle,
+    pub(crate) fields: Vec<FieldDef>,
+}
+
+pub(crate) enum ContainerData {
+    Struct(Vec<FieldDef>),
+    Enum(Vec<VariantDef>),
+}
+
+pub(crate) struct ContainerDef {
+    pub(crate) name: Ident,
+    pub(crate) generics: syn::Generics,
+    pub(crate) data: ContainerData,
+    pub(crate) new: NewConf,
+}
+
+impl Parse for ContainerDef {
+    fn parse(input: ParseStream) -> SynResult<Self> {
+        let input: DeriveInput = input.parse()?;
+        let new = parse_new_conf(&input.attrs)?;
+        let crate_conf = default_conf().lock().unwrap().clone();
+        let container_conf = {
+            let mut conf = FieldConf::default();
+            crate_conf.apply_to(&mut conf);
+            parse_property_attrs(&input.attrs, conf)?
+        };
+        let data = match input.data {
+            Data::Struct(data_struct) => match data_struct.fields {
+                Fields::Named(named) => {
+                    ContainerData::Struct(parse_named_fields(named, &container_conf)?)
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        input.ident,
+                        "`#[derive(Property)]` only supports structs with named fields",
+                    ))
+                }
+            },
+            Data::Enum(data_enum) => {
+                let variants = data_enum
+                    .variants
+                    .into_iter()
+                    .map(|variant| {
+                        let (style, fields) = match variant.fields {
+                            Fields::Named(named) => {
+                                (VariantStyle::Named, parse_named_fields(named, &container_conf)?)
+                            }
+                            Fields::Unnamed(_) => (VariantStyle::Unnamed, Vec::new()),
+                            Fields::Unit => (VariantStyle::Unit, Vec::new()),
+                        };
+                        Ok(VariantDef {
+                            ident: variant.ident,
+                            style,
+                            fields,
+                        })
+                    })
+                    .collect::<SynResult<Vec<_>>>()?;
+                ContainerData::Enum(variants)
+            }
+            Data::Union(_) => {
+                return Err(syn::Error::new_spanned(
+                    input.ident,
+                    "`#[derive(Property)]` does not support unions",
+                ))
+            }
+        };
+        Ok(ContainerDef {
+            name: input.ident,
+            generics: input.generics,
+            data,
+            new,
+        })
+    }
+}